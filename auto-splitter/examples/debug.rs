@@ -1,190 +1,45 @@
-#![feature(cstring_from_vec_with_nul)]
+//! A native, non-WASM harness for exercising the Mono traversal logic against a locally
+//! running copy of Celeste, without needing a LiveSplit frontend. Implements [`MemoryReader`]
+//! over `process_vm_readv` so it shares every bit of class/field/string logic with the
+//! shipped auto-splitter.
+
+use std::{mem, ptr, thread, time};
+
+use auto_splitter::cache::MetadataCache;
+use auto_splitter::mono::{MemoryReader, Mono, Result};
+use livesplit_wrapper::{Address, Error};
 use nix::sys::uio::{self, IoVec, RemoteIoVec};
 use nix::unistd::Pid;
-use num_bytes::FromBytes;
-use std::borrow::BorrowMut;
-use std::cell::RefCell;
-use std::collections::HashSet;
-use std::{ffi::CString, mem, ptr, slice, thread, time};
 use sysinfo::{ProcessExt, System, SystemExt};
 
-thread_local! {
-    static ACCESS: RefCell<HashSet<u64>> = RefCell::new(HashSet::new());
-    static TOTAL: RefCell<u64> = RefCell::new(0);
-}
-
-fn read_mem(pid: Pid, base: u64, len: u64, buf: &mut [u8]) -> nix::Result<usize> {
-    // println!("0x{:X} : {}", base, len);
-    let page = base % 4096;
-    TOTAL.with(|t| *t.borrow_mut() += 1);
-    ACCESS.with(|a| a.borrow_mut().insert(page));
-    let local = IoVec::from_mut_slice(buf);
-    let remote = RemoteIoVec {
-        base: base as usize,
-        len: len as usize,
-    };
-    uio::process_vm_readv(pid, &[local], &[remote])
-}
+/// A [`MemoryReader`] backed by `process_vm_readv` against an already-running process.
+struct PidReader(Pid);
 
-pub fn read<T: FromBytes<LEN>, const LEN: usize>(pid: Pid, base: u64) -> T {
-    let mut buf = [0; LEN];
-    read_mem(pid, base, 8, &mut buf).unwrap();
-    FromBytes::from_le_bytes(buf)
-}
-
-fn read_string(pid: Pid, base: u64) -> String {
-    const MAX_STR_LEN: usize = 256;
-    let mut buf = vec![0u8; MAX_STR_LEN];
-    read_mem(pid, base, MAX_STR_LEN as u64 - 1, &mut buf).unwrap();
-    buf.truncate(buf.iter().position(|&x| x == 0).unwrap() + 1);
-    let cstr = CString::from_vec_with_nul(buf).unwrap();
-    cstr.to_string_lossy().to_string()
-}
-
-fn class_name(pid: Pid, class: u64) -> String {
-    read_string(pid, read(pid, class + 0x40))
-}
-
-fn class_kind(pid: Pid, class: u64) -> MonoKind {
-    unsafe { mem::transmute(read::<u8, 1>(pid, class + 0x24) & 0b111) }
-}
-
-fn lookup_class(pid: Pid, cache: u64, name: &str) -> u64 {
-    let cache_table: u64 = read(pid, cache + 0x20);
-    let table_size: u32 = read(pid, cache + 0x18);
-    // println!("Searching for class {}", name);
-    // println!("Table size: {}, cache_table: {}", table_size, cache_table);
-    for bucket in 0..table_size {
-        let mut class = read(pid, cache_table + 8 * bucket as u64);
-        while class != 0 {
-            // println!("{:x} {:?}", class, class_name(pid, class));
-            if class_name(pid, class) == name {
-                return class;
-            }
-            class = read(pid, class + 0xf8);
-        }
+impl MemoryReader for PidReader {
+    fn read_into_buf(&self, addr: Address, buf: &mut [u8]) -> Result<()> {
+        let local = IoVec::from_mut_slice(buf);
+        let remote = RemoteIoVec { base: addr as usize, len: buf.len() };
+        uio::process_vm_readv(self.0, &[local], &[remote])
+            .map(|_| ())
+            .map_err(|_| Error::FailedRead)
     }
-    panic!("Couldn't find class: {}", name)
 }
 
-fn class_static_fields(pid: Pid, class: u64) -> u64 {
-    let vtable_size: u32 = read(pid, class + 0x54);
-    let runtime_info = read(pid, class + 0xc8);
-    let max_domains = read(pid, runtime_info);
-    // hack: assume this class is only valid in one domain
-    for i in 0..=max_domains {
-        let vtable: u64 = read(pid, runtime_info + 8 + 8 * i);
-        if vtable != 0 {
-            return read(pid, vtable + 64 + 8 * vtable_size as u64);
-        }
+impl PidReader {
+    /// Read several disjoint regions from the target in a single `process_vm_readv` syscall
+    /// instead of one syscall per region.
+    fn read_batch(&self, reads: Vec<(Address, &mut [u8])>) -> Result<()> {
+        let remotes: Vec<_> =
+            reads.iter().map(|(addr, buf)| RemoteIoVec { base: *addr as usize, len: buf.len() }).collect();
+        let locals: Vec<_> = reads.into_iter().map(|(_, buf)| IoVec::from_mut_slice(buf)).collect();
+        uio::process_vm_readv(self.0, &locals, &remotes)
+            .map(|_| ())
+            .map_err(|_| Error::FailedRead)
     }
-    panic!("Requested class isn't loaded");
-}
-
-#[allow(unused)]
-#[repr(u8)]
-enum MonoKind {
-    MonoClassDef = 1, // non-generic type
-    MonoClassGtd,     // generic type definition
-    MonoClassGinst,   // generic instantiation
-    MonoClassGparam,  // generic parameter
-    MonoClassArray,   // vector or array, bounded or not
-    MonoClassPointer, // pointer of function pointer
 }
 
-#[derive(Default, Copy, Clone)]
 #[repr(C)]
-struct MonoClassField {
-    ty: u64,
-    name: u64,
-    parent: u64,
-    offset: u32,
-}
-
-fn class_field_offset(pid: Pid, class: u64, name: &str) -> u32 {
-    let kind = class_kind(pid, class);
-    use MonoKind::*;
-    match kind {
-        MonoClassGinst => {
-            return class_field_offset(pid, read(pid, read(pid, class + 0xe0)), name);
-        }
-        MonoClassDef | MonoClassGtd => {}
-        _ => {
-            panic!("Something is wrong")
-        }
-    };
-    let num_fields: u32 = read(pid, class + 0xf0);
-    let fields_addr = read(pid, class + 0x90);
-    let mut fields = vec![MonoClassField::default(); num_fields as usize];
-    let total_size = mem::size_of::<MonoClassField>() as u64 * fields.len() as u64;
-    read_mem(pid, fields_addr, total_size, unsafe {
-        slice::from_raw_parts_mut::<u8>(
-            fields.as_mut_ptr() as *mut u8,
-            total_size as usize,
-        )
-    })
-    .unwrap();
-    for field in fields {
-        let temp = read_string(pid, field.name);
-        // TODO: maybe need a check for null terminated here?
-        if temp == name {
-            return field.offset;
-        }
-    }
-    panic!("Tried to lookup a nonexistent field: {}", name);
-}
-
-fn instance_class(pid: Pid, instance: u64) -> u64 {
-    read(pid, read(pid, instance & !1))
-}
-
-fn instance_field<T: FromBytes<LEN>, const LEN: usize>(
-    pid: Pid,
-    instance: u64,
-    name: &str,
-) -> T {
-    let class = instance_class(pid, instance);
-    let field_offset = class_field_offset(pid, class, name);
-    read::<T, LEN>(pid, instance + field_offset as u64)
-}
-fn static_field<T: FromBytes<LEN>, const LEN: usize>(
-    pid: Pid,
-    class: u64,
-    name: &str,
-) -> T {
-    let static_data = class_static_fields(pid, class);
-    let field_offset = class_field_offset(pid, class, name);
-    read::<T, LEN>(pid, static_data + field_offset as u64)
-}
-
-fn locate_splitter_info(pid: Pid, instance: u64) -> u64 {
-    let splitter_instance: u64 = instance_field(pid, instance, "AutoSplitterInfo");
-    splitter_instance + 0x10
-}
-
-fn read_boxed_string(pid: Pid, instance: u64) -> String {
-    let class = instance_class(pid, instance);
-    let data_offset = class_field_offset(pid, class, "m_firstChar");
-    let size_offset = class_field_offset(pid, class, "m_stringLength");
-    let size: u32 = read(pid, instance + size_offset as u64);
-    let mut oversize_buf = vec![0u8; size as usize * 2];
-    read_mem(
-        pid,
-        instance + data_offset as u64,
-        size as u64 * 2,
-        &mut oversize_buf,
-    )
-    .unwrap();
-    String::from_utf16_lossy(unsafe {
-        slice::from_raw_parts_mut::<u16>(
-            oversize_buf.as_mut_ptr() as *mut u16,
-            size as usize,
-        )
-    })
-}
-
-#[repr(C)]
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug)]
 struct AutoSplitterInfo {
     level: u64,
     chapter: i32,
@@ -202,112 +57,103 @@ struct AutoSplitterInfo {
     file_hearts: i32,
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Celeste {
-    pid: Pid,
-    instance: u64,
-    save_data_class: u64,
-    engine_class: u64,
-    level_class: u64,
-    info: u64,
-    prev_save: u64,
-    mode_stats: u64,
-}
-
-impl Celeste {
-    fn load_from_process(pid: Pid) -> Result<Self, &'static str> {
-        // let root_domain_addr = 0xA17650;
-        // let root_domain = read(pid, root_domain_addr);
-        let domain_list_addr = 0xA17698;
-        let domain_list = read(pid, domain_list_addr);
-        let first_domain: u64 = read(pid, domain_list);
-        let second_domain: u64 = read(pid, domain_list + 8);
-        let first_name = if first_domain != 0 {
-            read_string(pid, read(pid, first_domain + 0xd8))
-        } else {
-            String::new()
-        };
-        if first_name != "Celeste.exe" {
-            return Err("This is not Celeste!");
-        }
-        let celeste_domain = if second_domain != 0 {
-            // let second_name = read_string(pid, read(pid, first_domain + 0xd8));
-            // println!("Connected to: {}", second_name);
-            second_domain
-        } else {
-            // println!("Connected to: {}", first_name);
-            first_domain
-        };
-
-        let assembly: u64 = read(pid, celeste_domain + 0xd0);
-        let image: u64 = read(pid, assembly + 0x60);
-        let class_cache = image + 1216;
-        let celeste_class = lookup_class(pid, class_cache, "Celeste");
-        let celeste_instance = static_field(pid, celeste_class, "Instance");
-        Ok(Celeste {
-            pid,
-            instance: celeste_instance,
-            save_data_class: lookup_class(pid, class_cache, "SaveData"),
-            engine_class: lookup_class(pid, class_cache, "Engine"),
-            level_class: lookup_class(pid, class_cache, "Level"),
-            info: locate_splitter_info(pid, celeste_instance),
-            prev_save: 0,
-            mode_stats: 0,
-        })
+fn main() -> std::result::Result<(), &'static str> {
+    let s = System::new_all();
+    let candidates = s.process_by_name("Celeste.bin.x86");
+    let pid = Pid::from_raw(
+        match candidates[..] {
+            [] => Err("Couldn't find Celeste process"),
+            [p] => Ok(p),
+            [_, _, ..] => Err("Found more than one Celeste process"),
+        }?
+        .pid(),
+    );
+    println!("Found celeste process: {}", pid);
+    let proc = PidReader(pid);
+    let cache = MetadataCache::new();
+
+    let domain_list_addr = 0xA17698;
+    let domain_list = proc.read(domain_list_addr).map_err(|_| "failed to read domain list")?;
+    let first_domain: u64 = proc.read(domain_list).map_err(|_| "failed read")?;
+    let second_domain: u64 = proc.read(domain_list + 8).map_err(|_| "failed read")?;
+    let first_name = if first_domain != 0 {
+        let strloc: u64 = proc.read(first_domain + 0xd8).map_err(|_| "failed read")?;
+        proc.read_cstr(strloc).map_err(|_| "failed read")?
+    } else {
+        String::new()
+    };
+    if first_name != "Celeste.exe" {
+        return Err("This is not Celeste!");
     }
+    let celeste_domain = if second_domain != 0 { second_domain } else { first_domain };
+
+    let assembly: u64 = proc.read(celeste_domain + 0xd0).map_err(|_| "failed read")?;
+    let image: u64 = proc.read(assembly + 0x60).map_err(|_| "failed read")?;
+    let class_cache = image + 1216;
+    let celeste_class = cache.lookup_class(&proc, class_cache, "Celeste").ok_or("class not found")?;
+    let save_data = cache.lookup_class(&proc, class_cache, "SaveData").ok_or("class not found")?;
+    let engine = cache.lookup_class(&proc, class_cache, "Engine").ok_or("class not found")?;
+    let level = cache.lookup_class(&proc, class_cache, "Level").ok_or("class not found")?;
+    let celeste_instance: u64 =
+        cache.static_field(&proc, celeste_class, "Instance").ok_or("no instance")?;
+    let info_addr = proc.locate_splitter_info(celeste_instance).ok_or("no splitter info")?;
+    let info_size = mem::size_of::<AutoSplitterInfo>();
+    // The `SaveData.Instance` static field lives at a fixed address once the class's static
+    // storage and field offset are resolved, so it can be gathered into the same
+    // `process_vm_readv` call as the `AutoSplitterInfo` blob every tick.
+    let save_instance_addr = cache.static_field_addr(&proc, save_data, "Instance");
+    let mut prev_save = 0;
+    let mut mode_stats = 0;
+    let mut death_count: u32 = 0;
+    let mut checkpoint: u32 = 0;
+    loop {
+        thread::sleep(time::Duration::from_millis(500));
 
-    fn update(&mut self) -> nix::Result<Info> {
-        let info_size = mem::size_of::<AutoSplitterInfo>();
-        let mut buf = vec![0u8; info_size];
-        read_mem(self.pid, self.info, info_size as u64, &mut buf)?;
-        let asi: AutoSplitterInfo = unsafe { ptr::read(buf.as_ptr() as *const _) };
-
-        let current_level = if asi.level != 0 {
-            read_boxed_string(self.pid, asi.level)
-        } else {
-            String::new()
+        let mut info_buf = vec![0u8; info_size];
+        let mut save_addr_buf = [0u8; 8];
+        let read = match save_instance_addr {
+            Some(addr) => proc.read_batch(vec![(info_addr, &mut info_buf[..]), (addr, &mut save_addr_buf[..])]),
+            None => proc.read_into_buf(info_addr, &mut info_buf),
         };
+        if read.is_err() {
+            break;
+        }
+        let asi: AutoSplitterInfo = unsafe { ptr::read(info_buf.as_ptr() as *const _) };
 
-        let mut death_count = 0;
-        let mut checkpoint = 0;
+        let current_level =
+            if asi.level != 0 { proc.read_boxed_string(asi.level).unwrap_or_default() } else { String::new() };
 
-        let save_addr = static_field(self.pid, self.save_data_class, "Instance");
+        let save_addr = save_instance_addr
+            .map(|_| u64::from_le_bytes(save_addr_buf))
+            .unwrap_or_else(|| cache.static_field(&proc, save_data, "Instance").unwrap_or(0));
         if save_addr != 0 {
-            if save_addr != self.prev_save {
-                thread::sleep(time::Duration::from_secs(1));
-                self.prev_save = save_addr;
-                self.mode_stats = 0;
-                return self.update();
+            if save_addr != prev_save {
+                thread::sleep(time::Duration::from_secs(2));
+                prev_save = save_addr;
+                mode_stats = 0;
+                cache.invalidate();
+                continue;
             }
-            death_count = instance_field(self.pid, save_addr, "TotalDeaths");
+            death_count = cache.instance_field(&proc, save_addr, "TotalDeaths").unwrap_or(0);
             if asi.chapter == -1 {
-                self.mode_stats = 0;
-            } else if self.mode_stats == 0 {
-                let areas_obj: u64 = instance_field(self.pid, save_addr, "Areas");
-                let size: u32 = instance_field(self.pid, areas_obj, "_size");
-                let areas_arr: u64 = if size == 11 {
-                    // println!("Passed");
-                    instance_field(self.pid, areas_obj, "_items")
-                } else {
-                    // println!("Failed");
-                    0
-                };
+                mode_stats = 0;
+            } else if mode_stats == 0 {
+                let areas_obj: u64 = cache.instance_field(&proc, save_addr, "Areas").unwrap_or(0);
+                let size: u32 = cache.instance_field(&proc, areas_obj, "_size").unwrap_or(0);
+                let areas_arr: u64 =
+                    if size == 11 { cache.instance_field(&proc, areas_obj, "_items").unwrap_or(0) } else { 0 };
                 if areas_arr != 0 {
-                    // println!("Areas arr: {:x}", areas_arr);
                     let area_stats: u64 =
-                        read(self.pid, areas_arr + 0x20 + asi.chapter as u64 * 8);
-                    // println!("Area stats: {:x}", area_stats);
-                    let mode_arr =
-                        instance_field::<u64, 8>(self.pid, area_stats, "Modes") + 0x20;
-                    self.mode_stats = read(self.pid, mode_arr + asi.mode as u64 * 8);
+                        proc.read(areas_arr + 0x20 + asi.chapter as u64 * 8).unwrap_or(0);
+                    let mode_arr: u64 =
+                        cache.instance_field::<u64>(&proc, area_stats, "Modes").unwrap_or(0) + 0x20;
+                    mode_stats = proc.read(mode_arr + asi.mode as u64 * 8).unwrap_or(0);
                 }
             }
-            // println!("Mode stats: {:x}", self.mode_stats);
-            if self.mode_stats != 0 {
-                let checkpoints_obj =
-                    instance_field(self.pid, self.mode_stats, "Checkpoints");
-                // println!("checkpoint obj: {:x}", checkpoints_obj);
-                checkpoint = instance_field(self.pid, checkpoints_obj, "_count");
+            if mode_stats != 0 {
+                let checkpoints_obj: u64 =
+                    cache.instance_field(&proc, mode_stats, "Checkpoints").unwrap_or(0);
+                checkpoint = cache.instance_field(&proc, checkpoints_obj, "_count").unwrap_or(0);
             }
         }
 
@@ -315,20 +161,13 @@ impl Celeste {
             if !asi.chapter_started || asi.chapter_complete {
                 true
             } else {
-                let scene = read(
-                    self.pid,
-                    self.instance
-                        + class_field_offset(self.pid, self.engine_class, "scene") as u64,
-                );
-                if instance_class(self.pid, scene) != self.level_class {
+                let scene_field = cache.class_field_offset(&proc, engine, "scene").unwrap_or(0);
+                let scene: u64 = proc.read(celeste_instance + scene_field as u64).unwrap_or(0);
+                if proc.instance_class(scene) != Some(level) {
                     false
                 } else {
-                    let byte: u8 = read(
-                        self.pid,
-                        scene
-                            + class_field_offset(self.pid, self.level_class, "InCutscene")
-                                as u64,
-                    );
+                    let cutscene_field = cache.class_field_offset(&proc, level, "InCutscene").unwrap_or(0);
+                    let byte: u8 = proc.read(scene + cutscene_field as u64).unwrap_or(0);
                     byte != 0
                 }
             }
@@ -336,54 +175,11 @@ impl Celeste {
             false
         };
 
-        Ok(Info {
-            asi,
-            death_count,
-            checkpoint,
-            in_cutscene,
-            current_level,
-        })
-    }
-}
-
-#[allow(unused)]
-#[derive(Clone, Debug, Default)]
-struct Info {
-    asi: AutoSplitterInfo,
-    death_count: u32,
-    checkpoint: u32,
-    in_cutscene: bool,
-    current_level: String,
-}
-
-fn main() -> Result<(), &'static str> {
-    let s = System::new_all();
-    let candidates = s.process_by_name("Celeste.bin.x86");
-    let pid = Pid::from_raw(
-        match candidates[..] {
-            [] => Err("Couldn't find Celeste process"),
-            [p] => Ok(p),
-            [_, _, ..] => Err("Found more than one Celeste process"),
-        }?
-        .pid(),
-    );
-    println!("Found celeste process: {}", pid);
-
-    let mut celeste = Celeste::load_from_process(pid)?;
-    loop {
-        thread::sleep(time::Duration::from_millis(100));
-        let _info = celeste.update().unwrap();
-        let mut total = 0;
-        let mut count = 0;
-        TOTAL.with(|t| {
-            total = *t.borrow();
-            *t.borrow_mut() = 0
-        });
-        ACCESS.with(|a| {
-            count = a.borrow().len();
-            a.borrow_mut().clear()
-        });
-        println!("{} / {}", count, total);
-        // dbg!(info);
+        dbg!(in_cutscene);
+        dbg!(checkpoint);
+        dbg!(death_count);
+        dbg!(current_level);
+        dbg!(asi.file_time);
     }
+    Ok(())
 }
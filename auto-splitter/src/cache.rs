@@ -0,0 +1,84 @@
+//! A cache for Mono class and field-offset lookups.
+//!
+//! Every [`Mono::lookup_class`](crate::mono::Mono::lookup_class) call linearly scans a hash
+//! table, reading a class name per bucket entry, and every
+//! [`Mono::class_field_offset`](crate::mono::Mono::class_field_offset) call re-reads the
+//! *entire* `MonoClassField` array for that class. None of that changes between ticks, so
+//! [`MetadataCache`] memoizes both: class lookups by name, and field offsets by
+//! `(class, field name)`, the latter populated by one bulk read of the fields array rather
+//! than a re-read per field.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use bytemuck::Pod;
+use livesplit_wrapper::Address;
+
+use crate::mono::Mono;
+
+/// Memoized class and field-offset lookups for a single attached process. Share one of these
+/// across ticks; call [`invalidate`](MetadataCache::invalidate) on re-attach or whenever a
+/// save/instance pointer the splitter tracks changes, since that signals a reload where
+/// offsets may have moved.
+#[derive(Debug, Default)]
+pub struct MetadataCache {
+    classes: RefCell<HashMap<String, u64>>,
+    fields: RefCell<HashMap<u64, HashMap<String, u32>>>,
+}
+
+impl MetadataCache {
+    /// An empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop every memoized lookup, forcing the next access to re-resolve from the target
+    /// process.
+    pub fn invalidate(&self) {
+        self.classes.borrow_mut().clear();
+        self.fields.borrow_mut().clear();
+    }
+
+    /// Like [`Mono::lookup_class`], but memoized by class name.
+    pub fn lookup_class(&self, reader: &impl Mono, table: u64, name: &str) -> Option<u64> {
+        if let Some(&class) = self.classes.borrow().get(name) {
+            return Some(class);
+        }
+        let class = reader.lookup_class(table, name)?;
+        self.classes.borrow_mut().insert(name.to_owned(), class);
+        Some(class)
+    }
+
+    /// Like [`Mono::class_field_offset`], but memoized per class: the first lookup for any
+    /// field of a given class bulk-reads every field's name and offset in one go, and every
+    /// subsequent lookup (for that or any other field of the class) is a hash map hit.
+    pub fn class_field_offset(&self, reader: &impl Mono, class: u64, name: &str) -> Option<u32> {
+        if let Some(offset) = self.fields.borrow().get(&class).and_then(|f| f.get(name)) {
+            return Some(*offset);
+        }
+        let offsets = reader.class_field_offsets(class)?;
+        let offset = offsets.get(name).copied();
+        self.fields.borrow_mut().insert(class, offsets);
+        offset
+    }
+
+    /// Like [`Mono::instance_field`], but resolving the field offset through this cache.
+    pub fn instance_field<T: Pod>(&self, reader: &impl Mono, instance: u64, name: &str) -> Option<T> {
+        let class = reader.instance_class(instance)?;
+        let offset = self.class_field_offset(reader, class, name)?;
+        reader.read(instance + offset as u64).ok()
+    }
+
+    /// Like [`Mono::static_field`], but resolving the field offset through this cache.
+    pub fn static_field<T: Pod>(&self, reader: &impl Mono, class: u64, name: &str) -> Option<T> {
+        reader.read(self.static_field_addr(reader, class, name)?).ok()
+    }
+
+    /// The address of a static field itself (rather than its current value), for callers that
+    /// want to batch the read alongside others instead of going through [`Mono::read`].
+    pub fn static_field_addr(&self, reader: &impl Mono, class: u64, name: &str) -> Option<Address> {
+        let static_data = reader.class_static_fields(class)?;
+        let offset = self.class_field_offset(reader, class, name)?;
+        Some(static_data + offset as u64)
+    }
+}
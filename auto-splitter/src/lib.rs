@@ -1,11 +1,18 @@
+pub mod cache;
+pub mod mono;
+pub mod mono_handle;
+pub mod mono_struct;
 mod process;
 
 use std::{mem, ptr, time::Duration};
 
 use livesplit_wrapper::{HostFunctions, Process, Splitter};
 use log::*;
+use mono_derive::MonoStruct;
 
-use process::CelesteProcess;
+use cache::MetadataCache;
+use mono::Mono;
+use mono_struct::MonoStruct;
 
 #[derive(Default)]
 struct MySplitter {
@@ -73,7 +80,10 @@ impl Info {
     }
 }
 
-// can't use Pod to read this because it has bools and padding bytes
+// The fields of the object that the `Celeste` singleton's `AutoSplitterInfo` field points at,
+// starting just past its object header (see `Mono::locate_splitter_info`). They're read in one
+// go as a `#[repr(C)]` `ptr::read`, same as the native consumers (`mysplit`,
+// `examples/debug.rs`) read them.
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default)]
 struct AutoSplitterInfo {
@@ -95,6 +105,18 @@ struct AutoSplitterInfo {
     file_hearts: i32,
 }
 
+#[derive(Clone, Copy, Debug, Default, MonoStruct)]
+struct AreaStats {
+    #[mono(name = "Modes")]
+    modes: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, MonoStruct)]
+struct ModeStats {
+    #[mono(name = "Checkpoints")]
+    checkpoints: u64,
+}
+
 #[derive(Debug)]
 struct Celeste {
     proc: Process,
@@ -105,6 +127,7 @@ struct Celeste {
     info: u64,
     prev_save: u64,
     mode_stats: u64,
+    cache: MetadataCache,
 }
 
 impl Celeste {
@@ -122,37 +145,42 @@ impl Celeste {
         let mut death_count = 0;
         let mut checkpoint = 0;
 
-        let save_addr = self.proc.static_field(self.save_data_class, "Instance")?;
+        let save_addr = self.cache.static_field(&self.proc, self.save_data_class, "Instance")?;
         if save_addr != 0 {
             if save_addr != self.prev_save {
                 self.prev_save = save_addr;
                 self.mode_stats = 0;
+                self.cache.invalidate();
                 warn!("changed saves");
                 return None;
             }
-            death_count = self.proc.instance_field(save_addr, "TotalDeaths")?;
+            // `TotalDeaths` is read unconditionally, every tick, so it must not be bundled into
+            // a struct with fields (like `Areas`) that are only meaningful mid-chapter - a
+            // transient failure reading one of those would otherwise drop the death count too.
+            death_count = self.cache.instance_field(&self.proc, save_addr, "TotalDeaths")?;
             if asi.chapter == -1 {
                 self.mode_stats = 0;
             } else if self.mode_stats == 0 {
-                let areas_obj = self.proc.instance_field(save_addr, "Areas")?;
-                let size: u32 = self.proc.instance_field(areas_obj, "_size")?;
+                let areas: u64 = self.cache.instance_field(&self.proc, save_addr, "Areas")?;
+                let size: u32 = self.cache.instance_field(&self.proc, areas, "_size")?;
                 let areas_arr = if size == 11 {
-                    self.proc.instance_field(areas_obj, "_items")?
+                    self.cache.instance_field(&self.proc, areas, "_items")?
                 } else {
                     0
                 };
                 if areas_arr != 0 {
-                    let area_stats: u64 = self
+                    let area_stats_addr: u64 = self
                         .proc
                         .read(areas_arr + 0x20 + asi.chapter as u64 * 8)
                         .ok()?;
-                    let mode_arr = self.proc.instance_field::<u64>(area_stats, "Modes")? + 0x20;
+                    let area_stats = AreaStats::read_from(&self.proc, &self.cache, area_stats_addr)?;
+                    let mode_arr = area_stats.modes + 0x20;
                     self.mode_stats = self.proc.read(mode_arr + asi.mode as u64 * 8).ok()?;
                 }
             }
             if self.mode_stats != 0 {
-                let checkpoints_obj = self.proc.instance_field(self.mode_stats, "Checkpoints")?;
-                checkpoint = self.proc.instance_field(checkpoints_obj, "_count")?;
+                let mode = ModeStats::read_from(&self.proc, &self.cache, self.mode_stats)?;
+                checkpoint = self.cache.instance_field(&self.proc, mode.checkpoints, "_count")?;
             }
         }
 
@@ -161,14 +189,13 @@ impl Celeste {
         } else if !asi.chapter_started || asi.chapter_complete {
             true
         } else {
-            let scene_field = self.proc.class_field_offset(self.engine_class, "scene")?;
+            let scene_field = self.cache.class_field_offset(&self.proc, self.engine_class, "scene")?;
             let scene = self.proc.read(self.instance + scene_field as u64).ok()?;
             if self.proc.instance_class(scene)? != self.level_class {
                 false
             } else {
-                let in_cutscene = self
-                    .proc
-                    .class_field_offset(self.level_class, "InCutscene")?;
+                let in_cutscene =
+                    self.cache.class_field_offset(&self.proc, self.level_class, "InCutscene")?;
                 let bool: u8 = self.proc.read(scene + in_cutscene as u64).ok()?;
                 bool != 0
             }
@@ -225,6 +252,7 @@ impl MySplitter {
             info,
             prev_save: 0,
             mode_stats: 0,
+            cache: MetadataCache::new(),
         });
         Some(())
     }
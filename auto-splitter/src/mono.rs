@@ -0,0 +1,208 @@
+//! Mono-runtime introspection (class lookup, field offsets, boxed strings, ...) generic over
+//! anywhere bytes can be read from, so the same traversal code runs over the WASM
+//! [`Process`](livesplit_wrapper::Process) and a native debugger attached to a local process.
+
+use std::collections::HashMap;
+use std::mem;
+use std::slice;
+
+use bytemuck::Pod;
+use livesplit_wrapper::{Address, Error};
+
+/// A result from reading the attached process's memory.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Anywhere bytes can be read from by address. Implement this one method and [`Mono`]'s class
+/// and field traversal comes for free via its blanket impl.
+pub trait MemoryReader {
+    /// Read bytes starting at `addr` into `buf`.
+    fn read_into_buf(&self, addr: Address, buf: &mut [u8]) -> Result<()>;
+
+    /// Read a single `T` from `addr`.
+    fn read<T: Pod>(&self, addr: Address) -> Result<T> {
+        let mut buf = vec![0u8; mem::size_of::<T>()];
+        self.read_into_buf(addr, &mut buf)?;
+        // `buf` is a heap allocation with no particular alignment, so `bytemuck::from_bytes`
+        // (which asserts `align_of::<T>()`) would panic here more often than not.
+        // `pod_read_unaligned` copies the bytes out instead of reinterpreting them in place.
+        Ok(bytemuck::pod_read_unaligned(&buf))
+    }
+}
+
+#[allow(unused)]
+#[repr(u8)]
+pub(crate) enum MonoKind {
+    Def = 1, // non-generic type
+    Gtd,     // generic type definition
+    Ginst,   // generic instantiation
+    Gparam,  // generic parameter
+    Array,   // vector or array, bounded or not
+    Pointer, // pointer of function pointer
+}
+
+#[derive(Default, Copy, Clone)]
+#[repr(C)]
+struct MonoClassField {
+    ty: u64,
+    name: u64,
+    parent: u64,
+    offset: u32,
+}
+
+/// Mono/Unity runtime traversal: class lookup, field offsets, boxed strings. Blanket
+/// implemented for every [`MemoryReader`], so the WASM `Process` and any native reader share
+/// this one code path.
+pub trait Mono: MemoryReader {
+    /// Look up a loaded class by name in the given class cache (hash table).
+    fn lookup_class(&self, cache: u64, name: &str) -> Option<u64> {
+        let cache_table: u64 = self.read(cache + 0x20).ok()?;
+        let table_size: u32 = self.read(cache + 0x18).ok()?;
+        for bucket in 0..table_size {
+            let mut class = self.read(cache_table + 8 * bucket as u64).ok()?;
+            while class != 0 {
+                if self.class_name(class)? == name {
+                    return Some(class);
+                }
+                class = self.read(class + 0xf8).ok()?;
+            }
+        }
+        None
+    }
+
+    /// The kind of a class (plain type, generic instantiation, array, ...).
+    fn class_kind(&self, class: u64) -> Option<MonoKind> {
+        use MonoKind::*;
+        match self.read::<u8>(class + 0x24).ok()? & 0b111 {
+            1 => Some(Def),
+            2 => Some(Gtd),
+            3 => Some(Ginst),
+            4 => Some(Gparam),
+            5 => Some(Array),
+            6 => Some(Pointer),
+            // A class handle that doesn't point at a real `MonoClass` (a corrupt pointer, or a
+            // layout shift from a game update) - bail out rather than trusting an unvalidated
+            // discriminant.
+            _ => None,
+        }
+    }
+
+    /// The name of a class.
+    fn class_name(&self, class: u64) -> Option<String> {
+        self.read_cstr(self.read(class + 0x40).ok()?).ok()
+    }
+
+    /// The per-domain static field storage for a class.
+    fn class_static_fields(&self, class: u64) -> Option<u64> {
+        let vtable_size: u32 = self.read(class + 0x54).ok()?;
+        let runtime_info = self.read(class + 0xc8).ok()?;
+        let max_domains = self.read(runtime_info).ok()?;
+        // hack: assume this class is only valid in one domain
+        for i in 0..=max_domains {
+            let vtable: u64 = self.read(runtime_info + 8 + 8 * i).ok()?;
+            if vtable != 0 {
+                return self.read(vtable + 64 + 8 * vtable_size as u64).ok();
+            }
+        }
+        None
+    }
+
+    /// Every field name and offset for `class`, read in a single bulk read of the
+    /// `MonoClassField` array rather than one read per field. This is what
+    /// [`MetadataCache`](crate::cache::MetadataCache) memoizes.
+    fn class_field_offsets(&self, class: u64) -> Option<HashMap<String, u32>> {
+        let kind = self.class_kind(class)?;
+        use MonoKind::*;
+        let class = match kind {
+            Ginst => self.read(self.read(class + 0xe0).ok()?).ok()?,
+            Def | Gtd => class,
+            // Fields only make sense on a concrete or generic-definition class; anything else
+            // means a caller handed us the wrong kind of handle.
+            _ => return None,
+        };
+        let num_fields: u32 = self.read(class + 0xf0).ok()?;
+        let fields_addr = self.read(class + 0x90).ok()?;
+        let mut fields = vec![MonoClassField::default(); num_fields as usize];
+        let total_size = mem::size_of::<MonoClassField>() as u64 * fields.len() as u64;
+        self.read_into_buf(fields_addr, unsafe {
+            slice::from_raw_parts_mut::<u8>(fields.as_mut_ptr() as *mut u8, total_size as usize)
+        })
+        .ok()?;
+        fields
+            .into_iter()
+            .map(|field| Some((self.read_cstr(field.name).ok()?, field.offset)))
+            .collect()
+    }
+
+    /// The byte offset of a named field within instances of `class`.
+    fn class_field_offset(&self, class: u64, name: &str) -> Option<u32> {
+        self.class_field_offsets(class)?.get(name).copied()
+    }
+
+    /// The class of a live instance.
+    fn instance_class(&self, instance: u64) -> Option<u64> {
+        self.read(self.read(instance & !1).ok()?).ok()
+    }
+
+    /// Read a named instance field off a live object.
+    fn instance_field<T: Pod>(&self, instance: u64, name: &str) -> Option<T> {
+        let class = self.instance_class(instance)?;
+        let field_offset = self.class_field_offset(class, name)?;
+        self.read(instance + field_offset as u64).ok()
+    }
+
+    /// Read a named static field off a class.
+    fn static_field<T: Pod>(&self, class: u64, name: &str) -> Option<T> {
+        let static_data = self.class_static_fields(class)?;
+        let field_offset = self.class_field_offset(class, name)?;
+        self.read(static_data + field_offset as u64).ok()
+    }
+
+    /// Locate the `AutoSplitterInfo` blob off the `Celeste` singleton instance.
+    fn locate_splitter_info(&self, instance: u64) -> Option<u64> {
+        let splitter_instance: u64 = self.instance_field(instance, "AutoSplitterInfo")?;
+        Some(splitter_instance + 0x10)
+    }
+
+    /// Read a null-terminated string directly (not boxed in a Mono object).
+    fn read_cstr(&self, addr: Address) -> Result<String> {
+        const CHUNK_LEN: usize = 64;
+        const MAX_LEN: usize = 4096;
+        let mut bytes = Vec::new();
+        while bytes.len() < MAX_LEN {
+            let mut chunk = [0u8; CHUNK_LEN];
+            self.read_into_buf(addr + bytes.len() as u64, &mut chunk)?;
+            match chunk.iter().position(|&b| b == 0) {
+                Some(terminator) => {
+                    bytes.extend_from_slice(&chunk[..terminator]);
+                    return Ok(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                None => bytes.extend_from_slice(&chunk),
+            }
+        }
+        Err(Error::FailedRead)
+    }
+
+    /// Read a boxed Mono `string` instance's contents.
+    fn read_boxed_string(&self, instance: u64) -> Option<String> {
+        // No in-game string is anywhere near this long; a stale or corrupt `instance` pointer
+        // can otherwise hand back a `m_stringLength` that turns into a multi-gigabyte
+        // allocation instead of a failed read.
+        const MAX_STRING_LEN: u32 = 1 << 20;
+
+        let class = self.instance_class(instance)?;
+        let data_offset = self.class_field_offset(class, "m_firstChar")?;
+        let size_offset = self.class_field_offset(class, "m_stringLength")?;
+        let size: u32 = self.read(instance + size_offset as u64).ok()?;
+        if size > MAX_STRING_LEN {
+            return None;
+        }
+        let mut oversize_buf = vec![0u8; size as usize * 2];
+        self.read_into_buf(instance + data_offset as u64, &mut oversize_buf)
+            .ok()?;
+        Some(String::from_utf16_lossy(unsafe {
+            slice::from_raw_parts_mut::<u16>(oversize_buf.as_mut_ptr() as *mut u16, size as usize)
+        }))
+    }
+}
+
+impl<T: MemoryReader> Mono for T {}
@@ -0,0 +1,95 @@
+//! Object-style wrappers around the [`Mono`] trait's methods: an image you can
+//! look classes up in, a class you can read static fields or field offsets off, and an instance
+//! you can read fields or a boxed string off. These don't add any traversal logic of their own -
+//! they resolve class and field-offset lookups through a [`MetadataCache`] and just pair the
+//! result with the reader it came from, so callers outside this crate (a consumer targeting some
+//! other Mono game) can walk runtime state without re-deriving every `u64` by hand, depending on
+//! a `Celeste`-specific layout living in here, or re-resolving the same lookups every tick.
+//! The one exception is [`MonoInstance::boxed_string`], which is not cached.
+
+use bytemuck::Pod;
+
+use crate::cache::MetadataCache;
+use crate::mono::Mono;
+
+/// A loaded Mono image's class cache, ready to look classes up by name.
+pub struct MonoImage<'a, R> {
+    reader: &'a R,
+    cache: &'a MetadataCache,
+    class_cache: u64,
+}
+
+impl<'a, R: Mono> MonoImage<'a, R> {
+    /// Wrap an image's class cache (the hash table read by [`Mono::lookup_class`]) for lookups.
+    pub fn new(reader: &'a R, cache: &'a MetadataCache, class_cache: u64) -> Self {
+        MonoImage { reader, cache, class_cache }
+    }
+
+    /// Look up a loaded class by name.
+    pub fn class(&self, name: &str) -> Option<MonoClass<'a, R>> {
+        let handle = self.cache.lookup_class(self.reader, self.class_cache, name)?;
+        Some(MonoClass { reader: self.reader, cache: self.cache, handle })
+    }
+}
+
+/// A loaded Mono class, ready to read static fields or field offsets off.
+pub struct MonoClass<'a, R> {
+    reader: &'a R,
+    cache: &'a MetadataCache,
+    handle: u64,
+}
+
+impl<'a, R: Mono> MonoClass<'a, R> {
+    /// Read a named static field off this class.
+    pub fn static_field<T: Pod>(&self, name: &str) -> Option<T> {
+        self.cache.static_field(self.reader, self.handle, name)
+    }
+
+    /// The byte offset of a named field within instances of this class.
+    pub fn field_offset(&self, name: &str) -> Option<u32> {
+        self.cache.class_field_offset(self.reader, self.handle, name)
+    }
+
+    /// The raw class handle, for call sites that still need to hand it to a [`Mono`] method
+    /// directly (e.g. bulk field reads this wrapper doesn't cover).
+    pub fn handle(&self) -> u64 {
+        self.handle
+    }
+}
+
+/// A live Mono object instance, ready to read fields or a boxed string off.
+pub struct MonoInstance<'a, R> {
+    reader: &'a R,
+    cache: &'a MetadataCache,
+    handle: u64,
+}
+
+impl<'a, R: Mono> MonoInstance<'a, R> {
+    /// Wrap a live instance's address for field reads.
+    pub fn new(reader: &'a R, cache: &'a MetadataCache, handle: u64) -> Self {
+        MonoInstance { reader, cache, handle }
+    }
+
+    /// Read a named field off this instance.
+    pub fn field<T: Pod>(&self, name: &str) -> Option<T> {
+        self.cache.instance_field(self.reader, self.handle, name)
+    }
+
+    /// Read this instance as a boxed Mono `string`. This calls [`Mono::read_boxed_string`]
+    /// directly, so the string class's field offsets are re-resolved on every call rather than
+    /// through the [`MetadataCache`].
+    pub fn boxed_string(&self) -> Option<String> {
+        self.reader.read_boxed_string(self.handle)
+    }
+
+    /// This instance's class.
+    pub fn class(&self) -> Option<MonoClass<'a, R>> {
+        Some(MonoClass { reader: self.reader, cache: self.cache, handle: self.reader.instance_class(self.handle)? })
+    }
+
+    /// The raw instance handle, for call sites that still need to hand it to a [`Mono`] method
+    /// directly.
+    pub fn handle(&self) -> u64 {
+        self.handle
+    }
+}
@@ -0,0 +1,15 @@
+//! The trait backing `#[derive(MonoStruct)]` (from the sibling `mono-derive` crate): reads a
+//! struct's fields off a live Mono instance by field name instead of assuming the struct's Rust
+//! layout matches the game's, which drifts whenever a game update reorders or adds fields.
+
+use crate::cache::MetadataCache;
+use crate::mono::Mono;
+
+/// Implemented by structs annotated `#[derive(MonoStruct)]`, with each field tagged
+/// `#[mono(name = "...")]` giving its name in the Mono runtime. Don't implement this by hand.
+pub trait MonoStruct: Sized {
+    /// Read one instance of `Self` out of `instance`, resolving every field by name through
+    /// `cache` rather than a `#[repr(C)]` layout, so the struct stays correct across game
+    /// updates that reorder or insert fields.
+    fn read_from(reader: &impl Mono, cache: &MetadataCache, instance: u64) -> Option<Self>;
+}
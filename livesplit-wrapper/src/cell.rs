@@ -0,0 +1,29 @@
+//! A `no_std` replacement for `std::thread_local!` + `RefCell`, used for the handful of global
+//! singletons (the executor, the logger's line buffer, the registered splitter instance) that
+//! need exactly one instance for the life of the module.
+//!
+//! Autosplitters are single-threaded WASM modules, so there's never real concurrent access to
+//! guard against. [`StaticCell`] asserts that with an `unsafe impl Sync` so it can live in a
+//! plain `static`; interior mutability and borrow checking are still handled by the `RefCell`
+//! underneath, same as before.
+
+use core::cell::RefCell;
+
+/// A `static`-safe `RefCell<T>`. See the module docs for why the `unsafe impl Sync` is sound
+/// here.
+pub struct StaticCell<T>(RefCell<T>);
+
+unsafe impl<T> Sync for StaticCell<T> {}
+
+impl<T> StaticCell<T> {
+    /// Wrap `value` for storage in a `static`. `value` must be a `const` expression, same as
+    /// the inner expression of a `thread_local! { static X: RefCell<T> = ...; }` this replaces.
+    pub const fn new(value: T) -> Self {
+        StaticCell(RefCell::new(value))
+    }
+
+    /// Run `f` with the wrapped `RefCell`, mirroring `thread_local!`'s `X.with(|cell| ...)`.
+    pub fn with<R>(&self, f: impl FnOnce(&RefCell<T>) -> R) -> R {
+        f(&self.0)
+    }
+}
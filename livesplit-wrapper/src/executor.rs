@@ -0,0 +1,143 @@
+//! A trivial cooperative executor for writing autosplitters as a single linear
+//! `async fn` instead of a hand-rolled state machine.
+//!
+//! WASM here is single-threaded and every memory read is synchronous, so there's no need for
+//! a real scheduler: each `update()` tick we just poll the root future (and any spawned
+//! tasks) once with a no-op waker. [`yield_tick`] is the primitive that hands control back to
+//! LiveSplit between ticks - it's `Pending` the first time it's polled and `Ready` the next
+//! time, which is exactly "come back next tick".
+
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::Future;
+use core::mem;
+use core::pin::Pin;
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use crate::cell::StaticCell;
+use crate::Result;
+
+/// The root future of an autosplitter written with the async execution model. Implemented
+/// for any future that resolves to `()`, which is all you need to write one: `async fn`
+/// bodies already satisfy this.
+pub trait Run: Future<Output = ()> {}
+impl<T: Future<Output = ()>> Run for T {}
+
+/// A boxed, pinned [`Run`] future, as returned by [`crate::AsyncSplitter::run`].
+pub type BoxFuture = Pin<Box<dyn Run>>;
+
+struct Executor {
+    root: Option<BoxFuture>,
+    tasks: Vec<BoxFuture>,
+}
+
+static EXECUTOR: StaticCell<Executor> = StaticCell::new(Executor { root: None, tasks: Vec::new() });
+
+/// Install `future` as the root task, replacing whatever was previously running, and drop every
+/// task spawned by the previous run, so a second call starts from a clean slate rather than
+/// leaving the old root's spawned tasks (e.g. a death-counter watcher) running alongside
+/// whatever the new root spawns. Called from [`register_async_autosplitter!`]'s generated
+/// `configure()`. A timer `reset()` does not call it: the root future and its tasks keep
+/// running across resets.
+pub fn set_root(future: BoxFuture) {
+    EXECUTOR.with(|e| {
+        let mut e = e.borrow_mut();
+        e.root = Some(future);
+        e.tasks.clear();
+    });
+}
+
+/// Poll the root task and every spawned task exactly once. Call this from
+/// [`register_async_autosplitter!`]'s generated `update()`.
+///
+/// The root and task futures are taken out of the executor before polling them, rather than
+/// polled while holding `EXECUTOR`'s `RefCell` borrow - a task can call [`spawn`] on itself
+/// (the death-counter watcher pattern this was built for), and `spawn` needs its own borrow to
+/// push onto `tasks`. Polling under the same borrow would make that a `BorrowMutError`.
+pub fn poll() {
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut root = EXECUTOR.with(|e| e.borrow_mut().root.take());
+    if let Some(r) = &mut root {
+        if r.as_mut().poll(&mut cx).is_ready() {
+            root = None;
+        }
+    }
+    EXECUTOR.with(|e| e.borrow_mut().root = root);
+
+    let mut tasks = EXECUTOR.with(|e| mem::take(&mut e.borrow_mut().tasks));
+    tasks.retain_mut(|t| t.as_mut().poll(&mut cx).is_pending());
+    EXECUTOR.with(|e| e.borrow_mut().tasks.append(&mut tasks));
+}
+
+/// A future that is `Pending` the first time it's polled and `Ready` every time after,
+/// handing control back to LiveSplit for exactly one tick.
+pub struct YieldTick(bool);
+
+impl Future for YieldTick {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<()> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+}
+
+/// Suspend the current task until the next `update()` tick.
+pub fn yield_tick() -> YieldTick {
+    YieldTick(false)
+}
+
+/// Poll `pred` every tick until it returns `true`, yielding in between. If `pred` returns an
+/// `Err` (e.g. a failed memory read), it's propagated immediately rather than looping forever.
+pub async fn wait_until(mut pred: impl FnMut() -> Result<bool>) -> Result<()> {
+    while !pred()? {
+        yield_tick().await;
+    }
+    Ok(())
+}
+
+/// A handle to a task spawned with [`spawn`]. Await it with [`join`] to get its result once
+/// it completes.
+pub struct JoinHandle<T>(Rc<RefCell<Option<T>>>);
+
+/// Spawn an auxiliary task (e.g. a death-counter watcher) that's polled round-robin
+/// alongside the root task every tick until it completes.
+pub fn spawn<T: 'static>(future: impl Future<Output = T> + 'static) -> JoinHandle<T> {
+    let slot = Rc::new(RefCell::new(None));
+    let out = slot.clone();
+    let wrapped = async move {
+        let result = future.await;
+        *out.borrow_mut() = Some(result);
+    };
+    EXECUTOR.with(|e| e.borrow_mut().tasks.push(Box::pin(wrapped)));
+    JoinHandle(slot)
+}
+
+/// Yield until `handle`'s task completes, then return its result.
+pub async fn join<T>(handle: JoinHandle<T>) -> T {
+    loop {
+        if let Some(value) = handle.0.borrow_mut().take() {
+            return value;
+        }
+        yield_tick().await;
+    }
+}
+
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        raw()
+    }
+    fn no_op(_: *const ()) {}
+    fn raw() -> RawWaker {
+        RawWaker::new(core::ptr::null(), &VTABLE)
+    }
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, no_op, no_op, no_op);
+    unsafe { Waker::from_raw(raw()) }
+}
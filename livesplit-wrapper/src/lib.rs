@@ -1,4 +1,5 @@
 #![deny(missing_docs)]
+#![no_std]
 /*!
 A safe wrapper of the [livesplit-core](https://github.com/LiveSplit/livesplit-core) api for creating autosplitters.
 
@@ -41,14 +42,58 @@ impl Splitter for MySplitter {
     }
 }
 ```
+
+# Async autosplitters
+
+Splitting logic that's naturally a sequence of steps (wait for the file select screen, start,
+wait for the chapter to complete, split, ...) tends to turn into an awkward state machine when
+written against [`Splitter::update`]. As an alternative, implement [`AsyncSplitter`] and
+invoke [`register_async_autosplitter!`] instead: write `run()` as a single `async fn` that
+calls [`wait_until`] and yields with [`yield_tick`], and it'll be driven one poll per tick.
+
+# Logging
+
+Autosplitters run in WASM and so don't have `STDOUT`, which means `println!` and the usual
+`log` backends silently do nothing. Call [`init_logger`] (or [`init_default_logger`]) once at
+startup and `info!`/`warn!`/`error!` from the [`log`] crate will be routed to the frontend via
+[`HostFunctions::print`].
+
+# Settings
+
+Use [`Settings`] in [`Splitter::new`] to declare user-editable toggles and choices that the
+frontend renders and persists, then read them back each tick with
+[`HostFunctions::get_bool_setting`] / [`HostFunctions::get_choice_setting`].
 */
 // TODO: add link once livesplit-core provides a local debug runtime
+//
+// `no_std` because this runs as a `.wasm` module loaded into someone else's process - there's
+// no OS underneath it to provide one, and dragging in `std` just bloats the binary. `alloc` is
+// fine: the WASM runtime gives us a heap.
 
-use std::mem::{self, MaybeUninit};
-use std::slice;
+mod cell;
+mod executor;
+mod logger;
+mod settings;
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem::{self, MaybeUninit};
+use core::slice;
 
 use bytemuck::Pod;
 
+#[doc(hidden)]
+pub use cell::StaticCell;
+pub use executor::{join, spawn, wait_until, yield_tick, BoxFuture, JoinHandle, Run};
+#[doc(hidden)]
+pub use executor::{poll, set_root};
+pub use log::LevelFilter;
+pub use logger::{init_default_logger, init_logger};
+pub use settings::Settings;
+
 /// Wires up the necessary c interface for a type that implements [`Splitter`].
 ///
 /// If you defined `struct MySplitter {...}` and `impl Splitter for MySplitter {...}` then
@@ -56,17 +101,49 @@ use bytemuck::Pod;
 #[macro_export]
 macro_rules! register_autosplitter {
     ($struct:ident) => {
-        use std::cell::RefCell;
-        thread_local! {static SINGLETON: RefCell<$struct> = RefCell::default()}
+        static SINGLETON: $crate::StaticCell<Option<$struct>> = $crate::StaticCell::new(None);
         pub extern "C" fn configure() {
-            SINGLETON.with(|s| s.replace($struct::new()));
+            SINGLETON.with(|s| s.replace(Some($struct::new())));
         }
         pub extern "C" fn update() {
-            SINGLETON.with(|s| s.borrow_mut().update());
+            SINGLETON.with(|s| {
+                if let Some(s) = s.borrow_mut().as_mut() {
+                    s.update();
+                }
+            });
         }
     };
 }
 
+/// Wires up the C interface for an autosplitter written against the async execution model
+/// (see [`AsyncSplitter`]) instead of [`Splitter`].
+///
+/// Write `register_async_autosplitter!(MySplitter);` where `MySplitter` implements
+/// [`AsyncSplitter`], and LiveSplit will drive its root future one poll per tick.
+#[macro_export]
+macro_rules! register_async_autosplitter {
+    ($struct:ident) => {
+        pub extern "C" fn configure() {
+            $crate::set_root(<$struct as $crate::AsyncSplitter>::run());
+        }
+        pub extern "C" fn update() {
+            $crate::poll();
+        }
+    };
+}
+
+/// The entry point for an autosplitter written as a linear async script instead of a
+/// manually driven state machine, e.g.
+/// `start(); wait_until(|| Ok(chapter_complete)).await?; split();`.
+///
+/// Use [`register_async_autosplitter!`] to wire it up instead of [`register_autosplitter!`].
+pub trait AsyncSplitter {
+    /// Build the root future that will run for the life of the splitter. It's re-run from
+    /// scratch (along with any tasks it [`spawn`]ed) every time LiveSplit instantiates the
+    /// splitter, same as [`Splitter::new`].
+    fn run() -> BoxFuture;
+}
+
 /// Currently the only possible error is a failed memory read on the attached process.
 #[derive(Debug)]
 pub enum Error {
@@ -74,7 +151,7 @@ pub enum Error {
     FailedRead,
 }
 
-type Result<T> = std::result::Result<T, Error>;
+type Result<T> = core::result::Result<T, Error>;
 /// An address in the attached processes memory.
 ///
 /// Autosplitters can attach to 32-bit processes, they'll just get an error if they try to
@@ -122,6 +199,40 @@ impl Process {
                 .ok_or(Error::FailedRead)
         }
     }
+
+    /// Read `len` bytes from the attached process starting at `addr`.
+    pub fn read_into_vec(&self, addr: Address, len: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; len];
+        self.read_into_buf(addr, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Read a fixed-length, non-null-terminated string (e.g. a Pascal-style string whose
+    /// length is already known) starting at `addr`.
+    pub fn read_string(&self, addr: Address, len: usize) -> Result<String> {
+        Ok(String::from_utf8_lossy(&self.read_into_vec(addr, len)?).into_owned())
+    }
+
+    /// Read a null-terminated C string starting at `addr`. Reads in small chunks so this
+    /// works without knowing the string's length up front, and gives up (returning
+    /// [`Error::FailedRead`]) if no terminator turns up within a generous bound.
+    pub fn read_cstr(&self, addr: Address) -> Result<String> {
+        const CHUNK_LEN: usize = 64;
+        const MAX_LEN: usize = 4096;
+        let mut bytes = Vec::new();
+        while bytes.len() < MAX_LEN {
+            let mut chunk = [0u8; CHUNK_LEN];
+            self.read_into_buf(addr + bytes.len() as u64, &mut chunk)?;
+            match chunk.iter().position(|&b| b == 0) {
+                Some(terminator) => {
+                    bytes.extend_from_slice(&chunk[..terminator]);
+                    return Ok(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                None => bytes.extend_from_slice(&chunk),
+            }
+        }
+        Err(Error::FailedRead)
+    }
 }
 
 impl Drop for Process {
@@ -132,6 +243,39 @@ impl Drop for Process {
     }
 }
 
+/// A chain of pointer offsets used to locate a value through several levels of indirection,
+/// e.g. `base -> +0x18 (deref) -> +0x20 (deref) -> +0x8 (read T)`. This is how the majority of
+/// real autosplitters express where to find a value, rather than chasing pointers by hand
+/// every tick.
+#[derive(Debug, Clone)]
+pub struct PointerPath {
+    /// The address the chain starts from, before any offsets are applied.
+    pub base: Address,
+    /// Offsets to walk through, in order. Every offset but the last is added to the current
+    /// address and then dereferenced to get the next one; the last is added and then read as
+    /// `T` by [`deref`](PointerPath::deref).
+    pub offsets: Vec<u64>,
+}
+
+impl PointerPath {
+    /// Build a path relative to a module's base address, e.g.
+    /// `PointerPath::in_module(&process, "Celeste.bin.x86", vec![0x18, 0x20])`.
+    pub fn in_module(process: &Process, module: &str, offsets: Vec<u64>) -> Option<Self> {
+        Some(PointerPath { base: process.module(module)?, offsets })
+    }
+
+    /// Walk the chain: read a pointer at each intermediate offset, add the final offset, then
+    /// read a `T` there. Fails with [`Error::FailedRead`] if any hop fails.
+    pub fn deref<T: Pod>(&self, process: &Process) -> Result<T> {
+        let (&last, rest) = self.offsets.split_last().ok_or(Error::FailedRead)?;
+        let mut addr = self.base;
+        for &offset in rest {
+            addr = process.read::<Address>(addr + offset)?;
+        }
+        process.read(addr + last)
+    }
+}
+
 /// The main autosplitter trait.
 ///
 /// This trait is the entry point for the autosplitter's functionality. The `new` and
@@ -224,7 +368,7 @@ pub trait HostFunctions {
     /// Get the current state of the timer. This is how the autosplitter can detect if the
     /// player manually paused or reset a run.
     fn state(&self) -> TimerState {
-        unsafe { std::mem::transmute(ffi::get_timer_state() as u8) }
+        unsafe { core::mem::transmute(ffi::get_timer_state() as u8) }
     }
 
     /// Set a variable which can be displayed by LiveSplit. This is commonly used for
@@ -239,6 +383,19 @@ pub trait HostFunctions {
             );
         }
     }
+
+    /// Read the current value of a boolean setting declared through [`Settings`]. Cheap
+    /// enough to call every tick: it's a single FFI call with no allocation.
+    fn get_bool_setting(&self, key: &str) -> bool {
+        unsafe { ffi::settings_get_bool(key.as_ptr() as u32, key.len() as u32) != 0 }
+    }
+
+    /// Read the currently selected option of a choice setting declared through [`Settings`],
+    /// as its index into the `options` slice it was declared with. Cheap enough to call every
+    /// tick: it's a single FFI call with no allocation.
+    fn get_choice_setting(&self, key: &str) -> u32 {
+        unsafe { ffi::settings_get_choice(key.as_ptr() as u32, key.len() as u32) }
+    }
 }
 
 impl<T: Splitter> HostFunctions for T {}
@@ -273,5 +430,12 @@ mod ffi {
         pub(crate) fn resume_game_time();
         pub(crate) fn set_game_time(time: f64);
         pub(crate) fn get_timer_state() -> u32;
+        pub(crate) fn settings_add_title(label: u32, label_len: u32);
+        pub(crate) fn settings_add_bool(key: u32, key_len: u32, label: u32, label_len: u32, default: u32);
+        pub(crate) fn settings_add_choice(key: u32, key_len: u32, label: u32, label_len: u32, default: u32);
+        pub(crate) fn settings_add_choice_option(key: u32, key_len: u32, value: u32, value_len: u32);
+        pub(crate) fn settings_set_tooltip(key: u32, key_len: u32, tooltip: u32, tooltip_len: u32);
+        pub(crate) fn settings_get_bool(key: u32, key_len: u32) -> u32;
+        pub(crate) fn settings_get_choice(key: u32, key_len: u32) -> u32;
     }
 }
@@ -0,0 +1,71 @@
+//! A [`log`] backend that routes records through [`HostFunctions::print`](crate::HostFunctions::print).
+//!
+//! Mirrors the host's own stderr behavior: bytes are buffered and only flushed to
+//! `print_message` on a newline (or on an explicit `flush()`), so a multi-part log line isn't
+//! split across several frontend messages.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+use crate::cell::StaticCell;
+use crate::ffi;
+
+struct HostLogger;
+
+static BUFFER: StaticCell<String> = StaticCell::new(String::new());
+
+impl Log for HostLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            let _ = writeln!(buffer, "[{} {}] {}", record.level(), record.target(), record.args());
+            flush_complete_lines(&mut buffer);
+        });
+    }
+
+    fn flush(&self) {
+        BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            if !buffer.is_empty() {
+                print_line(&buffer);
+                buffer.clear();
+            }
+        });
+    }
+}
+
+fn flush_complete_lines(buffer: &mut String) {
+    while let Some(index) = buffer.find('\n') {
+        print_line(&buffer[..index]);
+        buffer.drain(..=index);
+    }
+}
+
+fn print_line(line: &str) {
+    unsafe { ffi::print_message(line.as_ptr(), line.len()) }
+}
+
+/// Install a [`HostLogger`] as the global logger, forwarding everything at `level` or above to
+/// [`HostFunctions::print`](crate::HostFunctions::print). Call this once from
+/// [`Splitter::new`](crate::Splitter::new) (or the start of [`AsyncSplitter::run`](crate::AsyncSplitter::run)) to make
+/// `log`-based debugging work.
+pub fn init_logger(level: LevelFilter) {
+    log::set_max_level(level);
+    // Only errors if a logger is already installed, which we don't expect and can't recover
+    // from anyway.
+    let _ = log::set_logger(&HostLogger);
+}
+
+/// Shorthand for `init_logger(LevelFilter::Info)`, the level most autosplitters want.
+pub fn init_default_logger() {
+    init_logger(Level::Info.to_level_filter());
+}
@@ -0,0 +1,93 @@
+//! User-editable settings (toggles, choices, section titles) that the LiveSplit frontend
+//! renders and persists on the player's behalf.
+
+use crate::ffi;
+
+/// A builder for declaring the schema of user-editable settings. Build one in
+/// [`Splitter::new`](crate::Splitter::new) - the frontend renders and persists whatever is
+/// declared here, and [`HostFunctions::get_bool_setting`](crate::HostFunctions::get_bool_setting)
+/// / [`HostFunctions::get_choice_setting`](crate::HostFunctions::get_choice_setting) read the
+/// current values back cheaply every tick.
+///
+/// ```ignore
+/// Settings::new()
+///     .title("General")
+///     .bool("split_on_cassettes", "Split on cassettes", false)
+///     .choice("category", "Category", &["Any%", "100%"], 0)
+///     .tooltip("category", "Which ruleset to split for");
+/// ```
+pub struct Settings(());
+
+impl Settings {
+    /// Start declaring the settings schema. There's no need to hold on to the result; each
+    /// builder method registers its setting with the host immediately.
+    pub fn new() -> Self {
+        Settings(())
+    }
+
+    /// Add a section heading, purely for organizing the settings UI.
+    pub fn title(self, label: &str) -> Self {
+        unsafe { ffi::settings_add_title(label.as_ptr() as u32, label.len() as u32) }
+        self
+    }
+
+    /// Add a boolean toggle, defaulting to `default` the first time it's shown.
+    pub fn bool(self, key: &str, label: &str, default: bool) -> Self {
+        unsafe {
+            ffi::settings_add_bool(
+                key.as_ptr() as u32,
+                key.len() as u32,
+                label.as_ptr() as u32,
+                label.len() as u32,
+                default as u32,
+            )
+        }
+        self
+    }
+
+    /// Add an enumerated choice between `options`, defaulting to `options[default]`. Read the
+    /// selected option back with
+    /// [`HostFunctions::get_choice_setting`](crate::HostFunctions::get_choice_setting), which
+    /// returns its index into `options` rather than an owned `String`.
+    pub fn choice(self, key: &str, label: &str, options: &[&str], default: usize) -> Self {
+        unsafe {
+            ffi::settings_add_choice(
+                key.as_ptr() as u32,
+                key.len() as u32,
+                label.as_ptr() as u32,
+                label.len() as u32,
+                default as u32,
+            )
+        }
+        for option in options {
+            unsafe {
+                ffi::settings_add_choice_option(
+                    key.as_ptr() as u32,
+                    key.len() as u32,
+                    option.as_ptr() as u32,
+                    option.len() as u32,
+                )
+            }
+        }
+        self
+    }
+
+    /// Attach a tooltip to a previously declared setting.
+    pub fn tooltip(self, key: &str, tooltip: &str) -> Self {
+        unsafe {
+            ffi::settings_set_tooltip(
+                key.as_ptr() as u32,
+                key.len() as u32,
+                tooltip.as_ptr() as u32,
+                tooltip.len() as u32,
+            )
+        }
+        self
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
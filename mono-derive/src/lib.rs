@@ -0,0 +1,95 @@
+//! `#[derive(MonoStruct)]`: generates a [`MonoStruct::read_from`](../auto_splitter/mono_struct/trait.MonoStruct.html)
+//! that reads each field of the annotated struct off a live Mono object by name, instead of
+//! `ptr::read`-ing a `#[repr(C)]` blob and hoping the game's field layout still matches.
+//!
+//! Every field needs a `#[mono(name = "...")]` giving its name in the Mono runtime:
+//!
+//! ```ignore
+//! #[derive(MonoStruct)]
+//! struct AutoSplitterInfo {
+//!     #[mono(name = "Level")]
+//!     level: u64,
+//!     #[mono(name = "TimerActive")]
+//!     timer_active: bool,
+//! }
+//! ```
+//!
+//! `bool` fields are read as a `u8` and compared against zero, since [`bytemuck::Pod`] isn't
+//! implemented for `bool` (not every bit pattern is a valid `bool`). Every other field type is
+//! read directly and must implement `Pod`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Lit, Meta, NestedMeta, Type};
+
+#[proc_macro_derive(MonoStruct, attributes(mono))]
+pub fn derive_mono_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => panic!("#[derive(MonoStruct)] only supports structs with named fields"),
+        },
+        _ => panic!("#[derive(MonoStruct)] only supports structs"),
+    };
+
+    let reads = fields.iter().map(|field| {
+        let ident = field.ident.as_ref().expect("checked above: named field");
+        let mono_name = mono_field_name(field);
+        if is_bool(&field.ty) {
+            quote! {
+                #ident: {
+                    let raw: u8 = cache.instance_field(reader, instance, #mono_name)?;
+                    raw != 0
+                }
+            }
+        } else {
+            quote! {
+                #ident: cache.instance_field(reader, instance, #mono_name)?
+            }
+        }
+    });
+
+    let expanded = quote! {
+        impl crate::mono_struct::MonoStruct for #name {
+            fn read_from(
+                reader: &impl crate::mono::Mono,
+                cache: &crate::cache::MetadataCache,
+                instance: u64,
+            ) -> Option<Self> {
+                Some(Self { #(#reads),* })
+            }
+        }
+    };
+
+    expanded.into()
+}
+
+fn mono_field_name(field: &Field) -> String {
+    for attr in &field.attrs {
+        if !attr.path.is_ident("mono") {
+            continue;
+        }
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for nested in list.nested {
+                if let NestedMeta::Meta(Meta::NameValue(nv)) = nested {
+                    if nv.path.is_ident("name") {
+                        if let Lit::Str(s) = nv.lit {
+                            return s.value();
+                        }
+                    }
+                }
+            }
+        }
+    }
+    panic!(
+        "field `{}` needs #[mono(name = \"...\")] giving its name in the Mono runtime",
+        field.ident.as_ref().unwrap(),
+    );
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(p) if p.path.is_ident("bool"))
+}
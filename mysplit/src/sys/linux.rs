@@ -0,0 +1,51 @@
+//! Linux process attachment via `sysinfo` and reads via `process_vm_readv`.
+
+use std::fs;
+
+use auto_splitter::mono::{MemoryReader, Result};
+use livesplit_wrapper::{Address, Error};
+use nix::sys::uio::{self, IoVec, RemoteIoVec};
+use nix::unistd::Pid;
+
+use super::{find_process_by_name, CELESTE_PROCESS_NAME};
+
+/// The root domain list's absolute address in `Celeste.bin.x86`, which ships as a non-PIE
+/// binary loaded at a fixed base - this is the same literal value the original baseline used
+/// directly as `domain_list_addr`, not an offset from any module's load address.
+const MONO_ROOT_DOMAIN_ADDR: Address = 0xA17698;
+
+/// A handle to an attached Celeste process on Linux.
+pub struct NativeProcess {
+    pid: Pid,
+}
+
+impl NativeProcess {
+    /// Find the running Celeste process by name and attach to it.
+    pub fn attach() -> Option<Self> {
+        Some(NativeProcess { pid: Pid::from_raw(find_process_by_name(CELESTE_PROCESS_NAME)?) })
+    }
+
+    /// The base address `name` (a shared object) is mapped at, read out of `/proc/<pid>/maps`.
+    pub fn module_base(&self, name: &str) -> Option<Address> {
+        let maps = fs::read_to_string(format!("/proc/{}/maps", self.pid)).ok()?;
+        let line = maps.lines().find(|line| line.ends_with(name))?;
+        let base = line.split('-').next()?;
+        u64::from_str_radix(base, 16).ok()
+    }
+
+    /// The Mono runtime's root domain list.
+    pub fn mono_root_domain(&self) -> Option<Address> {
+        Some(MONO_ROOT_DOMAIN_ADDR)
+    }
+}
+
+impl MemoryReader for NativeProcess {
+    fn read_into_buf(&self, addr: Address, buf: &mut [u8]) -> Result<()> {
+        let local = IoVec::from_mut_slice(buf);
+        let remote = RemoteIoVec { base: addr as usize, len: buf.len() };
+        match uio::process_vm_readv(self.pid, &[local], &[remote]) {
+            Ok(read) if read == buf.len() => Ok(()),
+            _ => Err(Error::FailedRead),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! macOS process attachment via `task_for_pid` and reads via `mach_vm_read_overwrite`.
+//!
+//! There's no syscall that hands back a module's base address directly, so
+//! [`NativeProcess::module_base`] walks the target's own dyld image list - read through this
+//! same [`MemoryReader`] - the same way a debugger would.
+
+use auto_splitter::mono::{MemoryReader, Mono, Result};
+use livesplit_wrapper::{Address, Error};
+use mach2::kern_return::KERN_SUCCESS;
+use mach2::port::mach_port_t;
+use mach2::task::task_info;
+use mach2::task_info::{task_dyld_info_data_t, TASK_DYLD_INFO, TASK_DYLD_INFO_COUNT};
+use mach2::traps::{mach_task_self, task_for_pid};
+use mach2::vm::mach_vm_read_overwrite;
+
+use super::{find_process_by_name, CELESTE_PROCESS_NAME};
+
+/// A handle to an attached Celeste process on macOS.
+pub struct NativeProcess {
+    task: mach_port_t,
+}
+
+/// Mirrors `dyld_all_image_infos`' header - just enough to walk `info_array`.
+#[repr(C)]
+struct DyldAllImageInfos {
+    version: u32,
+    info_array_count: u32,
+    info_array: u64,
+}
+
+/// Mirrors one entry of `dyld_all_image_infos.info_array`.
+#[repr(C)]
+struct DyldImageInfo {
+    load_address: u64,
+    file_path: u64,
+    file_mod_date: u64,
+}
+
+impl NativeProcess {
+    /// Find the running Celeste process by name and attach to it.
+    pub fn attach() -> Option<Self> {
+        let pid = find_process_by_name(CELESTE_PROCESS_NAME)?;
+        let mut task = 0;
+        let ok = unsafe { task_for_pid(mach_task_self(), pid, &mut task) };
+        (ok == KERN_SUCCESS).then(|| NativeProcess { task })
+    }
+
+    /// The base address `name` (a dylib) is loaded at, found by walking the target's own dyld
+    /// image list.
+    pub fn module_base(&self, name: &str) -> Option<Address> {
+        let mut info = task_dyld_info_data_t::default();
+        let mut count = TASK_DYLD_INFO_COUNT;
+        let ok = unsafe {
+            task_info(
+                self.task,
+                TASK_DYLD_INFO,
+                &mut info as *mut _ as *mut _,
+                &mut count,
+            )
+        };
+        if ok != KERN_SUCCESS {
+            return None;
+        }
+
+        let header: DyldAllImageInfos = self.read(info.all_image_info_addr).ok()?;
+        for i in 0..header.info_array_count as u64 {
+            let entry: DyldImageInfo =
+                self.read(header.info_array + i * std::mem::size_of::<DyldImageInfo>() as u64).ok()?;
+            let path = self.read_cstr(entry.file_path).ok()?;
+            if path.rsplit('/').next() == Some(name) {
+                return Some(entry.load_address);
+            }
+        }
+        None
+    }
+
+    /// The Mono runtime's root domain list. Unlike Linux's `Celeste.bin.x86`, we don't have a
+    /// verified address (or module offset) for the macOS `Celeste` build, so this honestly
+    /// reports "unsupported" rather than guessing and attaching to garbage.
+    pub fn mono_root_domain(&self) -> Option<Address> {
+        None
+    }
+}
+
+impl MemoryReader for NativeProcess {
+    fn read_into_buf(&self, addr: Address, buf: &mut [u8]) -> Result<()> {
+        let mut read_len: u64 = 0;
+        let ok = unsafe {
+            mach_vm_read_overwrite(self.task, addr, buf.len() as u64, buf.as_mut_ptr() as u64, &mut read_len)
+        };
+        if ok == KERN_SUCCESS && read_len as usize == buf.len() {
+            Ok(())
+        } else {
+            Err(Error::FailedRead)
+        }
+    }
+}
+
+impl Drop for NativeProcess {
+    fn drop(&mut self) {
+        unsafe {
+            mach2::port::mach_port_deallocate(mach_task_self(), self.task);
+        }
+    }
+}
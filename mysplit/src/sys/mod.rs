@@ -0,0 +1,50 @@
+//! Platform-specific process attachment and memory reads, mirroring how `std`'s own `sys`
+//! module swaps in a per-OS implementation behind a shared interface - here,
+//! [`auto_splitter::mono::MemoryReader`].
+//!
+//! Each platform module exposes a `NativeProcess` with an `attach()` constructor, a
+//! `module_base(name)` lookup, and a `mono_root_domain()` lookup. `mono_root_domain()` does not
+//! go through `module_base`: on Linux it returns a fixed absolute address, and on Windows and
+//! macOS it returns `None`.
+
+use sysinfo::{ProcessExt, System, SystemExt};
+
+/// Find the single running process named `name`, shared by every platform's `attach()` since
+/// the "which pid is Celeste" step is identical everywhere - only what you do with the pid
+/// afterwards (open a handle, attach a task port, ...) differs per OS.
+pub(crate) fn find_process_by_name(name: &str) -> Option<i32> {
+    let system = System::new_all();
+    match system.process_by_name(name).as_slice() {
+        [process] => Some(process.pid()),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use linux::NativeProcess;
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+pub use windows::NativeProcess;
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "macos")]
+pub use macos::NativeProcess;
+
+/// The name Celeste's executable is known under on this platform.
+#[cfg(target_os = "linux")]
+pub const CELESTE_PROCESS_NAME: &str = "Celeste.bin.x86";
+#[cfg(target_os = "windows")]
+pub const CELESTE_PROCESS_NAME: &str = "Celeste.exe";
+#[cfg(target_os = "macos")]
+pub const CELESTE_PROCESS_NAME: &str = "Celeste";
+
+// There's no single root-domain constant that works across platforms: the Linux value
+// (`MONO_ROOT_DOMAIN_ADDR` in `linux.rs`) is specific to the Linux build's image layout, and we don't have a verified equivalent for the
+// Windows or macOS binaries (different executables entirely, not just a different base). Each
+// platform module resolves (or honestly declines to resolve) `mono_root_domain` on its own
+// rather than sharing one constant here.
@@ -0,0 +1,109 @@
+//! Windows process attachment via `OpenProcess` and reads via `ReadProcessMemory`.
+
+use std::ffi::CStr;
+use std::os::raw::c_void;
+
+use auto_splitter::mono::{MemoryReader, Result};
+use livesplit_wrapper::{Address, Error};
+use winapi::shared::minwindef::{FALSE, HMODULE};
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::memoryapi::ReadProcessMemory;
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::psapi::{EnumProcessModulesEx, GetModuleBaseNameA, LIST_MODULES_ALL};
+use winapi::um::winnt::{HANDLE, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
+
+/// How many modules we're willing to enumerate per [`NativeProcess::module_base`] call.
+const MAX_MODULES: usize = 1024;
+
+use super::{find_process_by_name, CELESTE_PROCESS_NAME};
+
+/// A handle to an attached Celeste process on Windows.
+pub struct NativeProcess {
+    handle: HANDLE,
+}
+
+impl NativeProcess {
+    /// Find the running Celeste process by name and attach to it.
+    pub fn attach() -> Option<Self> {
+        let pid = find_process_by_name(CELESTE_PROCESS_NAME)? as u32;
+        let handle =
+            unsafe { OpenProcess(PROCESS_VM_READ | PROCESS_QUERY_INFORMATION, FALSE, pid) };
+        (!handle.is_null()).then(|| NativeProcess { handle })
+    }
+
+    /// The base address `name` (a DLL) is loaded at, found by enumerating the target's loaded
+    /// modules.
+    pub fn module_base(&self, name: &str) -> Option<Address> {
+        let mut modules = vec![std::ptr::null_mut::<c_void>() as HMODULE; MAX_MODULES];
+        let mut needed = 0;
+        let ok = unsafe {
+            EnumProcessModulesEx(
+                self.handle,
+                modules.as_mut_ptr(),
+                (modules.len() * std::mem::size_of::<HMODULE>()) as u32,
+                &mut needed,
+                LIST_MODULES_ALL,
+            )
+        };
+        if ok == 0 {
+            return None;
+        }
+        let count = (needed as usize / std::mem::size_of::<HMODULE>()).min(modules.len());
+        modules[..count].iter().find_map(|&module| {
+            let mut name_buf = [0u8; 256];
+            let len = unsafe {
+                GetModuleBaseNameA(
+                    self.handle,
+                    module,
+                    name_buf.as_mut_ptr() as *mut i8,
+                    name_buf.len() as u32,
+                )
+            };
+            // `GetModuleBaseNameA` can return `len == name_buf.len()` (the full capacity, with
+            // no room left for the nul) when the name doesn't fit, so index with `get` rather
+            // than a range that would panic out of bounds in that case.
+            (len > 0
+                && name_buf
+                    .get(..=len as usize)
+                    .and_then(|s| CStr::from_bytes_with_nul(s).ok())
+                    .map(|s| s.to_string_lossy() == name)
+                    .unwrap_or(false))
+            .then(|| module as Address)
+        })
+    }
+
+    /// The Mono runtime's root domain list. Unlike Linux's `Celeste.bin.x86`, we don't have a
+    /// verified address (or module offset) for the Windows `Celeste.exe` build, so this
+    /// honestly reports "unsupported" rather than guessing and attaching to garbage.
+    pub fn mono_root_domain(&self) -> Option<Address> {
+        None
+    }
+}
+
+impl MemoryReader for NativeProcess {
+    fn read_into_buf(&self, addr: Address, buf: &mut [u8]) -> Result<()> {
+        let mut read = 0;
+        let ok = unsafe {
+            ReadProcessMemory(
+                self.handle,
+                addr as *const c_void,
+                buf.as_mut_ptr() as *mut c_void,
+                buf.len(),
+                &mut read,
+            )
+        };
+        if ok != 0 && read == buf.len() {
+            Ok(())
+        } else {
+            Err(Error::FailedRead)
+        }
+    }
+}
+
+impl Drop for NativeProcess {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}